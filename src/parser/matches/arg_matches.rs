@@ -0,0 +1,57 @@
+// Std
+use std::any::Any;
+use std::ffi::OsStr;
+
+use super::matched_arg::GroupedValues;
+use crate::parser::ValueSource;
+use crate::util::Id;
+
+impl ArgMatches {
+    /// Non-flattened, per-occurrence values of `id`, lazily downcast to `T`.
+    ///
+    /// Each yielded inner iterator corresponds to one value group of the
+    /// argument, e.g. `cmd --file a b --file c d` yields `[[a, b], [c, d]]`
+    /// rather than a flattened `[a, b, c, d]`.
+    ///
+    /// Returns `None` if `id` wasn't matched, or if `T` doesn't match the
+    /// type `id`'s values were parsed as.
+    pub fn grouped_values_of<T: Any + Send + Sync + 'static>(
+        &self,
+        id: &str,
+    ) -> Option<GroupedValues<'_, T>> {
+        self.args.get(&Id::from(id))?.vals_of::<T>()
+    }
+
+    /// The [`ValueSource`] of the value at `index` for `id`, where `index`
+    /// lines up with [`ArgMatches::value_of`]/[`ArgMatches::values_of`]'s
+    /// flattened ordering rather than occurrence/value-group boundaries.
+    ///
+    /// Returns `None` if `id` wasn't matched or has no value at `index`.
+    pub fn value_source_of(&self, id: &str, index: usize) -> Option<ValueSource> {
+        self.args.get(&Id::from(id))?.value_source_of(index)
+    }
+
+    /// Each raw value of `id` paired with the argv index it was parsed from,
+    /// grouped the same way as [`ArgMatches::grouped_values_of`].
+    ///
+    /// A value whose group never recorded an index (e.g. it came from a
+    /// default rather than the command line) is left out of its group
+    /// rather than paired with the wrong index.
+    ///
+    /// Returns `None` if `id` wasn't matched.
+    pub fn indexed_values_of(&self, id: &str) -> Option<Vec<Vec<(usize, &OsStr)>>> {
+        let matched = self.args.get(&Id::from(id))?;
+        Some(
+            matched
+                .indexed_raw_vals()
+                .into_iter()
+                .map(|group| {
+                    group
+                        .into_iter()
+                        .map(|(idx, val)| (idx, val.as_os_str()))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
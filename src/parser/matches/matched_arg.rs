@@ -2,6 +2,7 @@
 use std::{
     ffi::{OsStr, OsString},
     iter::{Cloned, Flatten},
+    marker::PhantomData,
     slice::Iter,
 };
 
@@ -14,10 +15,20 @@ use crate::INTERNAL_ERROR_MSG;
 #[derive(Debug, Clone)]
 pub(crate) struct MatchedArg {
     occurs: u64,
-    ty: Option<ValueSource>,
+    // Flat, in the order indices were recorded, so `indices()`/`get_index()`
+    // stay O(1) instead of re-flattening a nested `Vec` on every lookup.
     indices: Vec<usize>,
+    // How many of `indices`' entries belong to each value group, in the same
+    // order as `vals`/`raw_vals`. A value group that never recorded an index
+    // (e.g. a default, which never goes through argv) simply has a `0` here,
+    // rather than borrowing an index that belongs to a later group.
+    indices_per_group: Vec<usize>,
     vals: Vec<Vec<AnyValue>>,
     raw_vals: Vec<Vec<OsString>>,
+    // One entry per value group, aligned with `vals`/`raw_vals`, so each
+    // group of values can be traced back to whether it came from the
+    // command line, an env var, or a default.
+    val_sources: Vec<ValueSource>,
     ignore_case: bool,
 }
 
@@ -25,10 +36,11 @@ impl MatchedArg {
     pub(crate) fn new() -> Self {
         MatchedArg {
             occurs: 0,
-            ty: None,
             indices: Vec::new(),
+            indices_per_group: Vec::new(),
             vals: Vec::new(),
             raw_vals: Vec::new(),
+            val_sources: Vec::new(),
             ignore_case: false,
         }
     }
@@ -46,11 +58,38 @@ impl MatchedArg {
     }
 
     pub(crate) fn get_index(&self, index: usize) -> Option<usize> {
-        self.indices.get(index).cloned()
+        self.indices.get(index).copied()
     }
 
     pub(crate) fn push_index(&mut self, index: usize) {
-        self.indices.push(index)
+        self.indices.push(index);
+        // Values create their own group counter via `push_val`/`new_val_group`;
+        // flags have no value group at all, so start one lazily here.
+        match self.indices_per_group.last_mut() {
+            Some(count) => *count += 1,
+            None => self.indices_per_group.push(1),
+        }
+    }
+
+    // Pairs each raw value with the argv index it was parsed from, grouped
+    // the same way as `raw_vals`. Pairing happens group-by-group, so a value
+    // whose group never recorded an index (e.g. a default applied without
+    // going through the argv parser) is simply left unpaired, instead of
+    // being matched up with an index that belongs to a different group.
+    pub(crate) fn indexed_raw_vals(&self) -> Vec<Vec<(usize, &OsString)>> {
+        let mut pos = 0;
+        self.raw_vals
+            .iter()
+            .zip(self.indices_per_group.iter())
+            .map(|(vals, &count)| {
+                let group_indices = &self.indices[pos..pos + count];
+                pos += count;
+                vals.iter()
+                    .zip(group_indices.iter())
+                    .map(|(val, idx)| (*idx, val))
+                    .collect()
+            })
+            .collect()
     }
 
     #[cfg(test)]
@@ -58,11 +97,27 @@ impl MatchedArg {
         self.raw_vals.iter()
     }
 
-    #[cfg(feature = "unstable-grouped")]
+    // Backs `vals_of`'s `GroupedValues` iterator.
     pub(crate) fn vals(&self) -> Iter<Vec<AnyValue>> {
         self.vals.iter()
     }
 
+    // Lazily yields each value group, downcast to `T`. Values sharing a
+    // `MatchedArg` all come from the same value parser, so checking the
+    // very first one is enough to catch a caller passing the wrong `T`;
+    // returns `None` rather than panicking on that mismatch.
+    pub(crate) fn vals_of<T: std::any::Any + Send + Sync + 'static>(
+        &self,
+    ) -> Option<GroupedValues<'_, T>> {
+        if let Some(first) = self.first() {
+            first.downcast_ref::<T>()?;
+        }
+        Some(GroupedValues {
+            iter: self.vals(),
+            marker: PhantomData,
+        })
+    }
+
     pub(crate) fn vals_flatten(&self) -> Flatten<Iter<Vec<AnyValue>>> {
         self.vals.iter().flatten()
     }
@@ -83,11 +138,13 @@ impl MatchedArg {
     pub(crate) fn push_val(&mut self, val: AnyValue, raw_val: OsString) {
         self.vals.push(vec![val]);
         self.raw_vals.push(vec![raw_val]);
+        self.indices_per_group.push(0);
     }
 
     pub(crate) fn new_val_group(&mut self) {
         self.vals.push(vec![]);
         self.raw_vals.push(vec![]);
+        self.indices_per_group.push(0);
     }
 
     pub(crate) fn append_val(&mut self, val: AnyValue, raw_val: OsString) {
@@ -118,32 +175,68 @@ impl MatchedArg {
     }
 
     pub(crate) fn check_explicit(&self, predicate: ArgPredicate) -> bool {
-        if self.ty == Some(ValueSource::DefaultValue) {
+        if !self.val_sources.is_empty()
+            && self
+                .val_sources
+                .iter()
+                .all(|s| *s == ValueSource::DefaultValue)
+        {
             return false;
         }
 
         match predicate {
-            ArgPredicate::Equals(val) => self.raw_vals_flatten().any(|v| {
-                if self.ignore_case {
-                    // If `v` isn't utf8, it can't match `val`, so `OsStr::to_str` should be fine
-                    eq_ignore_case(&v.to_string_lossy(), &val.to_string_lossy())
-                } else {
-                    OsString::as_os_str(v) == OsStr::new(val)
-                }
-            }),
+            ArgPredicate::Equals(val) => self
+                .raw_vals
+                .iter()
+                .zip(self.val_sources.iter())
+                .filter(|(_, source)| **source != ValueSource::DefaultValue)
+                .flat_map(|(group, _)| group.iter())
+                .any(|v| {
+                    if self.ignore_case {
+                        // If `v` isn't utf8, it can't match `val`, so `OsStr::to_str` should be fine
+                        eq_ignore_case(&v.to_string_lossy(), &val.to_string_lossy())
+                    } else {
+                        OsString::as_os_str(v) == OsStr::new(val)
+                    }
+                }),
             ArgPredicate::IsPresent => true,
         }
     }
 
     pub(crate) fn source(&self) -> Option<ValueSource> {
-        self.ty
+        self.val_sources.iter().copied().max()
+    }
+
+    // Returns the `ValueSource` of the value at `index`, where `index` counts
+    // over the flattened values (matching `vals_flatten`/`raw_vals_flatten`
+    // ordering), not over value groups — a group spanning multiple values
+    // (e.g. `--file a b` gathered into one group) reports the same source
+    // for every value it contains.
+    pub(crate) fn value_source_of(&self, index: usize) -> Option<ValueSource> {
+        let mut remaining = index;
+        for (group, source) in self.vals.iter().zip(self.val_sources.iter()) {
+            if remaining < group.len() {
+                return Some(*source);
+            }
+            remaining -= group.len();
+        }
+        None
     }
 
     pub(crate) fn update_ty(&mut self, ty: ValueSource) {
-        if let Some(existing) = self.ty {
-            self.ty = Some(existing.max(ty));
+        // `update_ty` may be called more than once for the same value group
+        // (e.g. once per value gathered); only start tracking a new group
+        // once `push_val`/`new_val_group` has actually created one. This is
+        // independent of `occurs`, since a single occurrence can push
+        // multiple value groups (e.g. `push_val` called once per value).
+        // When no value group exists at all, the source is still recorded;
+        // the first value group pushed afterwards then shares that entry.
+        if self.val_sources.len() < self.vals.len() {
+            self.val_sources.push(ty);
+        } else if let Some(last) = self.val_sources.last_mut() {
+            *last = (*last).max(ty);
         } else {
-            self.ty = Some(ty)
+            self.val_sources.push(ty);
         }
     }
 
@@ -152,6 +245,40 @@ impl MatchedArg {
     }
 }
 
+/// Lazily yields each value group of an argument, downcast to `T`.
+///
+/// Created by `MatchedArg::vals_of`; backs `ArgMatches::grouped_values_of`.
+/// Doesn't allocate beyond the iterator itself.
+pub struct GroupedValues<'a, T> {
+    iter: Iter<'a, Vec<AnyValue>>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: std::any::Any + Send + Sync + 'static> Iterator for GroupedValues<'a, T> {
+    type Item = Values<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|group| Values {
+            iter: group.iter(),
+            marker: PhantomData,
+        })
+    }
+}
+
+/// Lazily downcasts one value group's values to `T`.
+pub struct Values<'a, T> {
+    iter: Iter<'a, AnyValue>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: std::any::Any + Send + Sync + 'static> Iterator for Values<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().and_then(|v| v.downcast_ref::<T>())
+    }
+}
+
 impl Default for MatchedArg {
     fn default() -> Self {
         Self::new()
@@ -162,24 +289,27 @@ impl PartialEq for MatchedArg {
     fn eq(&self, other: &MatchedArg) -> bool {
         let MatchedArg {
             occurs: self_occurs,
-            ty: self_ty,
             indices: self_indices,
+            indices_per_group: self_indices_per_group,
             vals: _,
             raw_vals: self_raw_vals,
+            val_sources: self_val_sources,
             ignore_case: self_ignore_case,
         } = self;
         let MatchedArg {
             occurs: other_occurs,
-            ty: other_ty,
             indices: other_indices,
+            indices_per_group: other_indices_per_group,
             vals: _,
             raw_vals: other_raw_vals,
+            val_sources: other_val_sources,
             ignore_case: other_ignore_case,
         } = other;
         self_occurs == other_occurs
-            && self_ty == other_ty
             && self_indices == other_indices
+            && self_indices_per_group == other_indices_per_group
             && self_raw_vals == other_raw_vals
+            && self_val_sources == other_val_sources
             && self_ignore_case == other_ignore_case
     }
 }
@@ -233,4 +363,139 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_grouped_vals_typed() {
+        let mut m = MatchedArg::new();
+        m.push_val(AnyValue::new(String::from("aaa")), "aaa".into());
+        m.new_val_group();
+        m.append_val(AnyValue::new(String::from("bbb")), "bbb".into());
+        m.append_val(AnyValue::new(String::from("ccc")), "ccc".into());
+
+        let vals: Vec<Vec<&str>> = m
+            .vals_of::<String>()
+            .unwrap()
+            .map(|group| group.map(String::as_str).collect())
+            .collect();
+        assert_eq!(vals, vec![vec!["aaa"], vec!["bbb", "ccc"]]);
+    }
+
+    #[test]
+    fn test_grouped_vals_wrong_type_returns_none() {
+        let mut m = MatchedArg::new();
+        m.push_val(AnyValue::new(String::from("aaa")), "aaa".into());
+
+        assert!(m.vals_of::<i32>().is_none());
+    }
+
+    #[test]
+    fn test_value_source_per_value_group() {
+        let mut m = MatchedArg::new();
+
+        m.inc_occurrences();
+        m.push_val(AnyValue::new(String::from("default")), "default".into());
+        m.update_ty(ValueSource::DefaultValue);
+
+        m.inc_occurrences();
+        m.push_val(AnyValue::new(String::from("cli")), "cli".into());
+        m.update_ty(ValueSource::CommandLine);
+
+        assert_eq!(m.value_source_of(0), Some(ValueSource::DefaultValue));
+        assert_eq!(m.value_source_of(1), Some(ValueSource::CommandLine));
+        assert_eq!(m.value_source_of(2), None);
+        assert_eq!(m.source(), Some(ValueSource::CommandLine));
+
+        // A value sourced from the command line counts as an explicit match...
+        assert!(m.check_explicit(ArgPredicate::Equals(std::ffi::OsStr::new("cli"))));
+        // ...but a purely-default value, even if present, does not.
+        assert!(!m.check_explicit(ArgPredicate::Equals(std::ffi::OsStr::new("default"))));
+    }
+
+    #[test]
+    fn test_value_source_multi_value_single_occurrence() {
+        // A single occurrence whose two values are each
+        // pushed via their own `push_val` call (and so land in separate
+        // value groups), unlike `inc_occurrences` which only runs once.
+        let mut m = MatchedArg::new();
+        m.inc_occurrences();
+        m.push_val(AnyValue::new(String::from("a")), "a".into());
+        m.update_ty(ValueSource::CommandLine);
+        m.push_val(AnyValue::new(String::from("b")), "b".into());
+        m.update_ty(ValueSource::CommandLine);
+
+        assert_eq!(m.get_occurrences(), 1);
+        assert_eq!(m.value_source_of(0), Some(ValueSource::CommandLine));
+        assert_eq!(m.value_source_of(1), Some(ValueSource::CommandLine));
+        assert!(m.check_explicit(ArgPredicate::Equals(std::ffi::OsStr::new("b"))));
+    }
+
+    #[test]
+    fn test_value_source_mixed_within_arg() {
+        // A default value followed by an explicit env-sourced value on the
+        // same arg; each value group keeps its own, independent source.
+        let mut m = MatchedArg::new();
+        m.push_val(AnyValue::new(String::from("default")), "default".into());
+        m.update_ty(ValueSource::DefaultValue);
+        m.push_val(AnyValue::new(String::from("env")), "env".into());
+        m.update_ty(ValueSource::EnvVariable);
+
+        assert_eq!(m.value_source_of(0), Some(ValueSource::DefaultValue));
+        assert_eq!(m.value_source_of(1), Some(ValueSource::EnvVariable));
+        assert!(m.check_explicit(ArgPredicate::Equals(std::ffi::OsStr::new("env"))));
+        assert!(!m.check_explicit(ArgPredicate::Equals(std::ffi::OsStr::new("default"))));
+    }
+
+    #[test]
+    fn test_value_source_of_flattened_index() {
+        // `--file a b`: one occurrence, one value group holding both values
+        // (via `append_val`), so `value_source_of` must count over the
+        // flattened values rather than over groups to reach "b".
+        let mut m = MatchedArg::new();
+        m.new_val_group();
+        m.append_val(AnyValue::new(String::from("a")), "a".into());
+        m.append_val(AnyValue::new(String::from("b")), "b".into());
+        m.update_ty(ValueSource::CommandLine);
+
+        assert_eq!(m.value_source_of(0), Some(ValueSource::CommandLine));
+        assert_eq!(m.value_source_of(1), Some(ValueSource::CommandLine));
+        assert_eq!(m.value_source_of(2), None);
+    }
+
+    #[test]
+    fn test_indexed_vals_interleaved() {
+        // Mirrors `positional_multiple_3`: `test1 test2 test3 --flag`, where
+        // the positional's three values land at argv indices 1, 2 and 3.
+        let mut m = MatchedArg::new();
+        m.push_val(AnyValue::new(String::from("test1")), "test1".into());
+        m.push_index(1);
+        m.new_val_group();
+        m.append_val(AnyValue::new(String::from("test2")), "test2".into());
+        m.push_index(2);
+        m.append_val(AnyValue::new(String::from("test3")), "test3".into());
+        m.push_index(3);
+
+        let indexed = m.indexed_raw_vals();
+        assert_eq!(
+            indexed,
+            vec![
+                vec![(1, &OsString::from("test1"))],
+                vec![(2, &OsString::from("test2")), (3, &OsString::from("test3"))],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indexed_raw_vals_skips_missing_index() {
+        let mut m = MatchedArg::new();
+        // A default value is injected without ever touching argv, so its
+        // group never gets a `push_index` call.
+        m.push_val(AnyValue::new(String::from("default")), "default".into());
+        // A later, explicit CLI value does have a real argv index; it must
+        // not be paired with the earlier, index-less group.
+        m.push_val(AnyValue::new(String::from("cli")), "cli".into());
+        m.push_index(5);
+
+        let indexed = m.indexed_raw_vals();
+        assert_eq!(indexed, vec![vec![], vec![(5, &OsString::from("cli"))]]);
+    }
 }